@@ -0,0 +1,141 @@
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+struct GateSpec {
+    name: String,
+    tag: u8,
+    arity: u8,
+    param: bool,
+}
+
+fn parse_spec(contents: &str) -> Vec<GateSpec> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            GateSpec {
+                name: fields[0].to_string(),
+                tag: fields[1].parse().expect("tag must be a u8"),
+                arity: fields[2].parse().expect("arity must be 1 or 2"),
+                param: fields[3].parse().expect("param must be true or false"),
+            }
+        })
+        .collect()
+}
+
+/// Smallest bit width that can represent every tag in `0..count`.
+fn op_bits_for(count: usize) -> usize {
+    let mut bits = 1;
+    while (1usize << bits) < count {
+        bits += 1;
+    }
+    bits
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=gates.in");
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let spec_path = Path::new(&manifest_dir).join("gates.in");
+    let contents = fs::read_to_string(&spec_path).expect("failed to read gates.in");
+    let gates = parse_spec(&contents);
+
+    let mut constructors = String::new();
+    let mut from_name_arms = String::new();
+    let mut names = String::new();
+    let mut single_qubit_names = Vec::new();
+    let mut double_qubit_names = Vec::new();
+    let mut parametric_names = Vec::new();
+    let mut double_qubit_tags = Vec::new();
+    let mut parametric_tags = Vec::new();
+
+    for gate in &gates {
+        if gate.param {
+            writeln!(
+                constructors,
+                "    pub fn {name}(q: u32, theta: f64) -> Self {{ Gate::new_parametric({tag}, q, theta) }}",
+                name = gate.name,
+                tag = gate.tag,
+            )
+            .unwrap();
+            writeln!(
+                from_name_arms,
+                "        \"{name}\" => Some(Gate::{name}(q1, angle.unwrap_or(0.0))),",
+                name = gate.name,
+            )
+            .unwrap();
+            parametric_names.push(gate.name.clone());
+            parametric_tags.push(gate.tag.to_string());
+        } else if gate.arity == 2 {
+            writeln!(
+                constructors,
+                "    pub fn {name}(q1: u32, q2: u32) -> Self {{ Gate::new({tag}, q1, q2) }}",
+                name = gate.name,
+                tag = gate.tag,
+            )
+            .unwrap();
+            writeln!(
+                from_name_arms,
+                "        \"{name}\" => Some(Gate::{name}(q1, q2)),",
+                name = gate.name,
+            )
+            .unwrap();
+            double_qubit_names.push(gate.name.clone());
+            double_qubit_tags.push(gate.tag.to_string());
+        } else {
+            writeln!(
+                constructors,
+                "    pub fn {name}(q: u32) -> Self {{ Gate::new({tag}, q, 0) }}",
+                name = gate.name,
+                tag = gate.tag,
+            )
+            .unwrap();
+            writeln!(
+                from_name_arms,
+                "        \"{name}\" => Some(Gate::{name}(q1)),",
+                name = gate.name,
+            )
+            .unwrap();
+            single_qubit_names.push(gate.name.clone());
+        }
+        write!(names, "\"{}\", ", gate.name).unwrap();
+    }
+
+    let generated = format!(
+        "pub const GATE_COUNT: usize = {count};\n\
+         pub const GATE_NAMES: [&str; {count}] = [{names}];\n\
+         pub const SINGLE_QUBIT_NAMES: &str = \"{single}\";\n\
+         pub const DOUBLE_QUBIT_NAMES: &str = \"{double}\";\n\
+         pub const PARAMETRIC_NAMES: &str = \"{param}\";\n\
+         pub const DOUBLE_QUBIT_TAGS: &[u8] = &[{double_tags}];\n\
+         pub const PARAMETRIC_TAGS: &[u8] = &[{param_tags}];\n\
+         \n\
+         impl Gate {{\n\
+         {constructors}\
+         \n    pub const fn op_bits() -> usize {{ {op_bits} }}\n\
+         \n    pub fn from_name(name: &str, q1: u32, q2: u32, angle: Option<f64>) -> Option<Self> {{\n\
+         match name {{\n\
+         {from_name_arms}\
+         _ => None,\n\
+         }}\n\
+         }}\n\
+         }}\n",
+        count = gates.len(),
+        names = names,
+        single = single_qubit_names.join("|"),
+        double = double_qubit_names.join("|"),
+        param = parametric_names.join("|"),
+        double_tags = double_qubit_tags.join(", "),
+        param_tags = parametric_tags.join(", "),
+        op_bits = op_bits_for(gates.len()),
+        constructors = constructors,
+        from_name_arms = from_name_arms,
+    );
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("gates_generated.rs"), generated).unwrap();
+}
@@ -1,5 +1,8 @@
 use pyo3::prelude::*;
 pub mod qasm_parser;
+#[cfg(feature = "disasm")]
+pub mod qasm_writer;
+pub mod simulator;
 pub mod structures;
 
 pub fn my_function() -> String {
@@ -9,21 +9,35 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 2 {
-        println!("Usage: cargo run <path.qasm>");
+        println!("Usage: cargo run <path.qasm> | cargo run <path.dqasm> [out.qasm]");
         return Ok(());
     }
-    let out_path = "out.dqasm";
-
     let path = &args[1];
     let file = File::open(path)?;
     let mut reader = BufReader::new(file);
-    let out_file = File::create(out_path)?;
+
     if path.ends_with(".qasm") {
+        let out_path = "out.dqasm";
         let circuit = parallel_parse_qasm(reader)?;
+        let out_file = File::create(out_path)?;
         let mut writer = BufWriter::new(out_file);
         circuit.write(&mut writer)?;
+    } else if args.len() > 2 && args[2].ends_with(".qasm") {
+        #[cfg(feature = "disasm")]
+        {
+            let circuit = Circuit::read(&mut reader)?;
+            let out_file = File::create(&args[2])?;
+            let mut writer = BufWriter::new(out_file);
+            circuit.write_qasm(&mut writer)?;
+        }
+        #[cfg(not(feature = "disasm"))]
+        {
+            println!("Disassembly to .qasm requires the `disasm` feature");
+        }
     } else {
+        let out_path = "out.dqasm";
         let circuit = Circuit::read(&mut reader)?;
+        let out_file = File::create(out_path)?;
         let mut writer = BufWriter::new(out_file);
         circuit.write(&mut writer)?;
     }
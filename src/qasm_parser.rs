@@ -5,21 +5,45 @@ use std::{
     io::{self, BufRead},
 };
 
-use crate::structures::{Circuit, Gate};
+use crate::structures::{
+    Circuit, Gate, RegisterDescriptor, DOUBLE_QUBIT_NAMES, PARAMETRIC_NAMES, SINGLE_QUBIT_NAMES,
+};
 
 pub fn parallel_parse_qasm<R: BufRead>(reader: R) -> io::Result<Circuit> {
     let mut offset: u32 = 0;
+    let mut classical_offset: u32 = 0;
     let mut register_groups: HashMap<String, u32> = HashMap::new();
+    let mut classical_groups: HashMap<String, u32> = HashMap::new();
+    let mut registers: Vec<RegisterDescriptor> = Vec::new();
 
     // For capturing the qubit register names
     let qreg_re = Regex::new(r"^(qreg)\s+([a-zA-Z_][a-zA-Z0-9_]*)\[(\d+)\];$").unwrap();
+    // For capturing classical register names
+    let creg_re = Regex::new(r"^(creg)\s+([a-zA-Z_][a-zA-Z0-9_]*)\[(\d+)\];$").unwrap();
     let mut gate_lines: Vec<String> = Vec::new();
     for line in reader.lines().flatten() {
         if let Some(caps) = qreg_re.captures(&line) {
             let name = caps[2].to_string();
             let size: u32 = caps[3].parse().unwrap();
+            registers.push(RegisterDescriptor {
+                name: name.clone(),
+                size,
+                offset,
+                is_classical: false,
+            });
             register_groups.insert(name, offset);
             offset += size;
+        } else if let Some(caps) = creg_re.captures(&line) {
+            let name = caps[2].to_string();
+            let size: u32 = caps[3].parse().unwrap();
+            registers.push(RegisterDescriptor {
+                name: name.clone(),
+                size,
+                offset: classical_offset,
+                is_classical: true,
+            });
+            classical_groups.insert(name, classical_offset);
+            classical_offset += size;
         } else {
             gate_lines.push(line);
         }
@@ -30,35 +54,69 @@ pub fn parallel_parse_qasm<R: BufRead>(reader: R) -> io::Result<Circuit> {
         .cloned()
         .collect::<Vec<_>>()
         .join("|");
-    // Regex for capturing cx gates
+    let classical_keys = classical_groups
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("|");
+    // Regex for capturing double-qubit gates (generated name list)
     let cx_re = Regex::new(&format!(
-        r"^(cx)\s+({})\[(\d+)\],\s*({})\[(\d+)\];$",
-        keys, keys
+        r"^({})\s+({})\[(\d+)\],\s*({})\[(\d+)\];$",
+        DOUBLE_QUBIT_NAMES, keys, keys
+    ))
+    .unwrap();
+    // Regex for capturing single-qubit, non-parametric gates (generated name list)
+    let t_re = Regex::new(&format!(r"^({})\s+({})\[(\d+)\];$", SINGLE_QUBIT_NAMES, keys)).unwrap();
+    // Regex for capturing parametric rotation gates (generated name list)
+    let rot_re = Regex::new(&format!(
+        r"^({})\(({})\)\s+({})\[(\d+)\];$",
+        PARAMETRIC_NAMES, ANGLE_PATTERN, keys
+    ))
+    .unwrap();
+    // Regex for capturing measurement into a classical register
+    let measure_re = Regex::new(&format!(
+        r"^measure\s+({})\[(\d+)\]\s*->\s*({})\[(\d+)\];$",
+        keys, classical_keys
     ))
     .unwrap();
-    // Regex for capturing t or tdg gates
-    let t_re = Regex::new(&format!(r"^(t|tdg)\s+({})\[(\d+)\];$", keys)).unwrap();
 
     let gates: Vec<Gate> = gate_lines
         .par_iter()
         .filter_map(|line| {
-            if let Some(caps) = cx_re.captures(&line) {
+            if let Some(caps) = cx_re.captures(line) {
                 let q0 = caps[3].parse::<u32>().unwrap() + register_groups[&caps[2]];
                 let q1 = caps[5].parse::<u32>().unwrap() + register_groups[&caps[4]];
-                Some(Gate::cx(q0, q1))
-            } else if let (Some(caps)) = t_re.captures(&line) {
+                Gate::from_name(&caps[1], q0, q1, None)
+            } else if let Some(caps) = t_re.captures(line) {
                 let q0 = caps[3].parse::<u32>().unwrap() + register_groups[&caps[2]];
-                match caps.get(1).unwrap().as_str() {
-                    "t" => Some(Gate::t(q0)),
-                    "tdg" => Some(Gate::tdg(q0)),
-                    _ => None,
-                }
+                Gate::from_name(&caps[1], q0, 0, None)
+            } else if let Some(caps) = rot_re.captures(line) {
+                let angle = parse_angle(&caps[2]);
+                let q0 = caps[4].parse::<u32>().unwrap() + register_groups[&caps[3]];
+                Gate::from_name(&caps[1], q0, 0, Some(angle))
+            } else if let Some(caps) = measure_re.captures(line) {
+                let q0 = caps[2].parse::<u32>().unwrap() + register_groups[&caps[1]];
+                let c0 = caps[4].parse::<u32>().unwrap() + classical_groups[&caps[3]];
+                Gate::from_name("measure", q0, c0, None)
             } else {
                 None
             }
         })
         .collect();
     let mut circ = Circuit::new();
+    registers.into_iter().for_each(|r| circ.add_register(r));
     gates.into_iter().for_each(|g| circ.add_gate(g));
     Ok(circ)
 }
+
+/// Matches a bare float literal or `pi`/`-pi`; full expressions (`pi/2`, …)
+/// are not supported.
+const ANGLE_PATTERN: &str = r"-?pi|-?\d+(?:\.\d+)?(?:[eE][-+]?\d+)?";
+
+fn parse_angle(s: &str) -> f64 {
+    match s {
+        "pi" => std::f64::consts::PI,
+        "-pi" => -std::f64::consts::PI,
+        other => other.parse::<f64>().unwrap(),
+    }
+}
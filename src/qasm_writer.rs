@@ -0,0 +1,125 @@
+use std::io;
+
+use crate::structures::{Circuit, GATE_NAMES, PARAMETRIC_TAGS};
+
+#[cfg(feature = "disasm")]
+impl Circuit {
+    /// Writes `self` out as OpenQASM text. When `self.registers` carries the
+    /// original `qreg`/`creg` layout (format `version >= 4`), declarations
+    /// and operand indices are qualified against it; otherwise every qubit
+    /// is flattened into a single anonymous `q` register.
+    pub fn write_qasm<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "OPENQASM 2.0;")?;
+        writeln!(writer, "include \"qelib1.inc\";")?;
+
+        if self.registers.is_empty() {
+            let size = self.qubits.iter().max().map_or(0, |&m| m + 1);
+            writeln!(writer, "qreg q[{}];", size)?;
+        } else {
+            for register in &self.registers {
+                let kind = if register.is_classical { "creg" } else { "qreg" };
+                writeln!(writer, "{} {}[{}];", kind, register.name, register.size)?;
+            }
+        }
+
+        for gate in &self.gates {
+            if let Some(bit) = gate.measured_bit() {
+                let (q0, _) = gate.get_qubits();
+                let (qreg, qidx) = self.qualify(q0, false);
+                let (creg, cidx) = self.qualify(bit, true);
+                writeln!(writer, "measure {}[{}] -> {}[{}];", qreg, qidx, creg, cidx)?;
+                continue;
+            }
+
+            let (q0, maybe_q1) = gate.get_qubits();
+            let (qreg0, qidx0) = self.qualify(q0, false);
+            match maybe_q1 {
+                Some(q1) => {
+                    let (qreg1, qidx1) = self.qualify(q1, false);
+                    let name = gate_name(gate.gate_type)?;
+                    writeln!(
+                        writer,
+                        "{} {}[{}],{}[{}];",
+                        name, qreg0, qidx0, qreg1, qidx1
+                    )?;
+                }
+                None => {
+                    let name = gate_name(gate.gate_type)?;
+                    if PARAMETRIC_TAGS.contains(&gate.gate_type) {
+                        writeln!(
+                            writer,
+                            "{}({}) {}[{}];",
+                            name,
+                            gate.angle.unwrap_or(0.0),
+                            qreg0,
+                            qidx0
+                        )?;
+                    } else {
+                        writeln!(writer, "{} {}[{}];", name, qreg0, qidx0)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolves a global qubit/classical-bit index back to its declared
+    /// register name and local index, falling back to an anonymous `q`/`c`
+    /// register when no matching descriptor was recorded.
+    fn qualify(&self, global_index: u32, classical: bool) -> (String, u32) {
+        for register in &self.registers {
+            if register.is_classical == classical
+                && global_index >= register.offset
+                && global_index < register.offset + register.size
+            {
+                return (register.name.clone(), global_index - register.offset);
+            }
+        }
+
+        let fallback = if classical { "c" } else { "q" };
+        (fallback.to_string(), global_index)
+    }
+}
+
+/// Looks up `gate_type`'s QASM keyword in the `gates.in`-generated
+/// `GATE_NAMES` table instead of hand-matching each tag here.
+#[cfg(feature = "disasm")]
+fn gate_name(gate_type: u8) -> io::Result<&'static str> {
+    GATE_NAMES.get(gate_type as usize).copied().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown gate type {}", gate_type),
+        )
+    })
+}
+
+#[cfg(all(test, feature = "disasm"))]
+mod tests {
+    use crate::qasm_parser::parallel_parse_qasm;
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trips_qasm_through_write_qasm() {
+        let source = "OPENQASM 2.0;\n\
+                       include \"qelib1.inc\";\n\
+                       qreg q[2];\n\
+                       creg c[2];\n\
+                       h q[0];\n\
+                       cx q[0],q[1];\n\
+                       measure q[0] -> c[0];\n";
+
+        let circuit = parallel_parse_qasm(Cursor::new(source)).unwrap();
+
+        let mut out = Vec::new();
+        circuit.write_qasm(&mut out).unwrap();
+        let reparsed = parallel_parse_qasm(Cursor::new(out)).unwrap();
+
+        assert_eq!(reparsed.gates.len(), circuit.gates.len());
+        for (original, roundtripped) in circuit.gates.iter().zip(reparsed.gates.iter()) {
+            assert_eq!(original.gate_type, roundtripped.gate_type);
+            assert_eq!(original.qubit1, roundtripped.qubit1);
+            assert_eq!(original.qubit2, roundtripped.qubit2);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::io;
+
+use num_complex::Complex64;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::structures::{Circuit, Gate};
+
+/// Simulating more qubits than this would need a state vector larger than
+/// 2^28 complex amplitudes (4 GiB at 16 bytes each), so we refuse instead.
+const MAX_SIMULATED_QUBITS: u32 = 28;
+
+impl Circuit {
+    /// Runs `self` against a state vector initialized to |0...0> and
+    /// returns the final amplitudes, indexed by basis state with `qubit1`
+    /// of the lowest-numbered qubit in the least significant bit.
+    pub fn simulate(&self) -> io::Result<Vec<Complex64>> {
+        // `self.qubits` only tracks which indices are *used*, not how many
+        // there are; a circuit touching only `q[2]` still needs a 3-qubit
+        // state vector so that gate's basis-state math stays in bounds.
+        let num_qubits = self.qubits.iter().max().map_or(0, |&m| m + 1);
+        if num_qubits > MAX_SIMULATED_QUBITS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "refusing to simulate {} qubits (max {})",
+                    num_qubits, MAX_SIMULATED_QUBITS
+                ),
+            ));
+        }
+
+        let mut state = vec![Complex64::new(0.0, 0.0); 1usize << num_qubits];
+        state[0] = Complex64::new(1.0, 0.0);
+        for gate in &self.gates {
+            apply_gate(&mut state, gate);
+        }
+
+        Ok(state)
+    }
+
+    /// Simulates `self` and draws `shots` bitstrings from the resulting
+    /// distribution `|amplitude|^2`, keyed by basis state index. `seed`
+    /// drives the sampling RNG so results are reproducible.
+    pub fn sample(&self, shots: usize, seed: u64) -> io::Result<HashMap<u64, u64>> {
+        let state = self.simulate()?;
+        let probabilities: Vec<f64> = state.iter().map(Complex64::norm_sqr).collect();
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut counts: HashMap<u64, u64> = HashMap::new();
+        for _ in 0..shots {
+            let sample: f64 = rng.gen();
+            let mut cumulative = 0.0;
+            let mut outcome = probabilities.len() - 1;
+            for (basis_state, probability) in probabilities.iter().enumerate() {
+                cumulative += probability;
+                if sample < cumulative {
+                    outcome = basis_state;
+                    break;
+                }
+            }
+            *counts.entry(outcome as u64).or_insert(0) += 1;
+        }
+
+        Ok(counts)
+    }
+}
+
+type SingleQubitMatrix = [[Complex64; 2]; 2];
+
+fn apply_gate(state: &mut [Complex64], gate: &Gate) {
+    match gate.gate_type {
+        0 => apply_single_qubit(state, gate.qubit1, t_matrix()),
+        1 => apply_cx(state, gate.qubit1, gate.qubit2),
+        2 => apply_single_qubit(state, gate.qubit1, h_matrix()),
+        3 => apply_single_qubit(state, gate.qubit1, s_matrix()),
+        4 => apply_single_qubit(state, gate.qubit1, tdg_matrix()),
+        5 => apply_single_qubit(state, gate.qubit1, rx_matrix(gate.angle.unwrap_or(0.0))),
+        6 => apply_single_qubit(state, gate.qubit1, ry_matrix(gate.angle.unwrap_or(0.0))),
+        7 => apply_single_qubit(state, gate.qubit1, rz_matrix(gate.angle.unwrap_or(0.0))),
+        _ => {}
+    }
+}
+
+/// Applies `matrix` to every pair of amplitudes whose basis states differ
+/// only in `target`'s bit.
+fn apply_single_qubit(state: &mut [Complex64], target: u32, matrix: SingleQubitMatrix) {
+    let target_bit = 1usize << target;
+    for i in 0..state.len() {
+        if i & target_bit == 0 {
+            let j = i | target_bit;
+            let (a, b) = (state[i], state[j]);
+            state[i] = matrix[0][0] * a + matrix[0][1] * b;
+            state[j] = matrix[1][0] * a + matrix[1][1] * b;
+        }
+    }
+}
+
+/// Flips `target`'s bit on every basis state whose `control` bit is 1.
+fn apply_cx(state: &mut [Complex64], control: u32, target: u32) {
+    let control_bit = 1usize << control;
+    let target_bit = 1usize << target;
+    for i in 0..state.len() {
+        if i & control_bit != 0 && i & target_bit == 0 {
+            state.swap(i, i | target_bit);
+        }
+    }
+}
+
+fn h_matrix() -> SingleQubitMatrix {
+    let f = std::f64::consts::FRAC_1_SQRT_2;
+    [
+        [Complex64::new(f, 0.0), Complex64::new(f, 0.0)],
+        [Complex64::new(f, 0.0), Complex64::new(-f, 0.0)],
+    ]
+}
+
+fn s_matrix() -> SingleQubitMatrix {
+    [
+        [Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)],
+        [Complex64::new(0.0, 0.0), Complex64::new(0.0, 1.0)],
+    ]
+}
+
+fn t_matrix() -> SingleQubitMatrix {
+    let (sin, cos) = std::f64::consts::FRAC_PI_4.sin_cos();
+    [
+        [Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)],
+        [Complex64::new(0.0, 0.0), Complex64::new(cos, sin)],
+    ]
+}
+
+fn tdg_matrix() -> SingleQubitMatrix {
+    let (sin, cos) = std::f64::consts::FRAC_PI_4.sin_cos();
+    [
+        [Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)],
+        [Complex64::new(0.0, 0.0), Complex64::new(cos, -sin)],
+    ]
+}
+
+fn rx_matrix(theta: f64) -> SingleQubitMatrix {
+    let (sin, cos) = (theta / 2.0).sin_cos();
+    [
+        [Complex64::new(cos, 0.0), Complex64::new(0.0, -sin)],
+        [Complex64::new(0.0, -sin), Complex64::new(cos, 0.0)],
+    ]
+}
+
+fn ry_matrix(theta: f64) -> SingleQubitMatrix {
+    let (sin, cos) = (theta / 2.0).sin_cos();
+    [
+        [Complex64::new(cos, 0.0), Complex64::new(-sin, 0.0)],
+        [Complex64::new(sin, 0.0), Complex64::new(cos, 0.0)],
+    ]
+}
+
+fn rz_matrix(theta: f64) -> SingleQubitMatrix {
+    let (sin, cos) = (theta / 2.0).sin_cos();
+    [
+        [Complex64::new(cos, -sin), Complex64::new(0.0, 0.0)],
+        [Complex64::new(0.0, 0.0), Complex64::new(cos, sin)],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::Circuit;
+
+    const EPSILON: f64 = 1e-9;
+
+    #[test]
+    fn bell_state_has_equal_weight_on_00_and_11() {
+        let mut circuit = Circuit::new();
+        circuit.add_gate(Gate::h(0));
+        circuit.add_gate(Gate::cx(0, 1));
+
+        let state = circuit.simulate().unwrap();
+        assert_eq!(state.len(), 4);
+        assert!((state[0].norm_sqr() - 0.5).abs() < EPSILON);
+        assert!((state[3].norm_sqr() - 0.5).abs() < EPSILON);
+        assert!(state[1].norm_sqr() < EPSILON);
+        assert!(state[2].norm_sqr() < EPSILON);
+    }
+
+    #[test]
+    fn sample_is_reproducible_for_a_fixed_seed() {
+        let mut circuit = Circuit::new();
+        circuit.add_gate(Gate::h(0));
+        circuit.add_gate(Gate::cx(0, 1));
+
+        let first = circuit.sample(256, 42).unwrap();
+        let second = circuit.sample(256, 42).unwrap();
+        assert_eq!(first, second);
+
+        // Bell state only ever lands on |00> (0) or |11> (3).
+        let total: u64 = first.values().sum();
+        assert_eq!(total, 256);
+        assert!(first.keys().all(|&outcome| outcome == 0 || outcome == 3));
+    }
+}
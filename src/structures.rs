@@ -1,22 +1,48 @@
 use std::collections::HashSet;
 use std::io;
 
+/// Describes one `qreg`/`creg` declaration so a disassembler pass can
+/// reconstruct the original register layout instead of a single anonymous
+/// register. `offset` is the register's base index into the global qubit
+/// (or, for classical registers, classical bit) address space.
+#[derive(Debug, Clone)]
+pub struct RegisterDescriptor {
+    pub name: String,
+    pub size: u32,
+    pub offset: u32,
+    pub is_classical: bool,
+}
+
 pub struct Header {
     pub magic: [u8; 6],
     pub version: u16,
     pub num_qubits: u32,
     pub num_gates: u64,
+    /// Size of the classical address space `measure` gates index into
+    /// (`version >= 4`), independent of `num_qubits`. Persisted rather than
+    /// re-derived from `registers` on read, since a circuit built without
+    /// classical registers (e.g. programmatically) still needs its
+    /// `measure` gates decoded with the right field width.
+    pub num_classical: u32,
+    pub registers: Vec<RegisterDescriptor>,
 }
 impl Header {
     const fn dqasm_magic() -> &'static [u8; 6] {
         b"DQASM\0"
     }
-    pub fn new(num_qubits: u32, num_gates: u64) -> Self {
+    pub fn new(
+        num_qubits: u32,
+        num_gates: u64,
+        num_classical: u32,
+        registers: Vec<RegisterDescriptor>,
+    ) -> Self {
         Self {
             magic: *Header::dqasm_magic(),
-            version: 1,
+            version: 4,
             num_qubits: num_qubits,
             num_gates: num_gates,
+            num_classical,
+            registers,
         }
     }
 
@@ -25,6 +51,18 @@ impl Header {
         writer.write_all(&self.version.to_le_bytes())?;
         writer.write_all(&self.num_qubits.to_le_bytes())?;
         writer.write_all(&self.num_gates.to_le_bytes())?;
+        writer.write_all(&self.num_classical.to_le_bytes())?;
+
+        writer.write_all(&(self.registers.len() as u32).to_le_bytes())?;
+        for register in &self.registers {
+            let name_bytes = register.name.as_bytes();
+            writer.write_all(&(name_bytes.len() as u16).to_le_bytes())?;
+            writer.write_all(name_bytes)?;
+            writer.write_all(&[register.is_classical as u8])?;
+            writer.write_all(&register.size.to_le_bytes())?;
+            writer.write_all(&register.offset.to_le_bytes())?;
+        }
+
         Ok(())
     }
 
@@ -50,109 +88,144 @@ impl Header {
         reader.read_exact(&mut buf8)?;
         let num_gates = u64::from_le_bytes(buf8);
 
+        let mut num_classical = 0u32;
+        let mut registers = Vec::new();
+        if version >= 4 {
+            let mut classical_buf = [0u8; 4];
+            reader.read_exact(&mut classical_buf)?;
+            num_classical = u32::from_le_bytes(classical_buf);
+
+            let mut count_buf = [0u8; 4];
+            reader.read_exact(&mut count_buf)?;
+            let register_count = u32::from_le_bytes(count_buf);
+
+            for _ in 0..register_count {
+                let mut name_len_buf = [0u8; 2];
+                reader.read_exact(&mut name_len_buf)?;
+                let name_len = u16::from_le_bytes(name_len_buf) as usize;
+
+                let mut name_buf = vec![0u8; name_len];
+                reader.read_exact(&mut name_buf)?;
+                let name = String::from_utf8(name_buf)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+                let mut is_classical_buf = [0u8; 1];
+                reader.read_exact(&mut is_classical_buf)?;
+                let is_classical = is_classical_buf[0] != 0;
+
+                let mut size_buf = [0u8; 4];
+                reader.read_exact(&mut size_buf)?;
+                let size = u32::from_le_bytes(size_buf);
+
+                let mut offset_buf = [0u8; 4];
+                reader.read_exact(&mut offset_buf)?;
+                let offset = u32::from_le_bytes(offset_buf);
+
+                registers.push(RegisterDescriptor {
+                    name,
+                    size,
+                    offset,
+                    is_classical,
+                });
+            }
+        }
+
         Ok(Header {
             magic,
             version,
             num_qubits,
             num_gates,
+            num_classical,
+            registers,
         })
     }
 }
 
-/// Gate types:
-///
-/// 0: T
-///
-/// 1: CX
-///
-/// 2: H
-///
-/// 3: S
+/// Gate type tags, constructors, `op_bits()` and the QASM name tables below
+/// are generated from `gates.in` by `build.rs` — see `GATE_NAMES` and the
+/// `impl Gate` block included at the bottom of this file.
 #[derive(Debug)]
 pub struct Gate {
     pub gate_type: u8,
     pub qubit1: u32,
     pub qubit2: u32,
+    pub angle: Option<f64>,
 }
 impl Gate {
-    const fn op_bits() -> usize {
-        /*
-        2 bits to represent:
-        0: T
-        1: CX
-        2: H
-        3: S
-         */
-        2
+    const fn angle_bits() -> usize {
+        64
     }
 
+    /// Opcode width used by the legacy `version = 1` layout, pinned to what
+    /// `Gate::op_bits()` returned when that layout was introduced (chunk0-2,
+    /// 4 gate types). `op_bits()` has since grown with `gates.in`, so v1
+    /// files must not be decoded with the current value.
+    const LEGACY_V1_OP_BITS: usize = 2;
+
     fn new(gate_type: u8, qubit1: u32, qubit2: u32) -> Self {
         Gate {
             gate_type,
             qubit1,
             qubit2,
+            angle: None,
         }
     }
 
-    pub fn t(q: u32) -> Self {
-        Gate::new(0, q, 0)
+    fn new_parametric(gate_type: u8, qubit1: u32, angle: f64) -> Self {
+        Gate {
+            gate_type,
+            qubit1,
+            qubit2: 0,
+            angle: Some(angle),
+        }
     }
 
-    pub fn cx(q1: u32, q2: u32) -> Self {
-        Gate::new(1, q1, q2)
+    pub fn is_double_qubit(&self) -> bool {
+        DOUBLE_QUBIT_TAGS.contains(&self.gate_type)
     }
 
-    pub fn h(q: u32) -> Self {
-        Gate::new(2, q, 0)
-    }
+    /// Tag of the `measure` entry in `gates.in`; kept in sync manually
+    /// since build.rs only tracks arity and parametricity, not semantics.
+    const MEASURE_TAG: u8 = 8;
 
-    pub fn s(q: u32) -> Self {
-        Gate::new(3, q, 0)
+    pub fn is_measurement(&self) -> bool {
+        self.gate_type == Gate::MEASURE_TAG
     }
 
-    pub fn is_double_qubit(&self) -> bool {
-        self.gate_type == 1
+    /// The classical bit a `measure` gate writes to, as a global index into
+    /// `Circuit::registers`' classical address space.
+    pub fn measured_bit(&self) -> Option<u32> {
+        self.is_measurement().then_some(self.qubit2)
     }
 
     pub fn get_qubits(&self) -> (u32, Option<u32>) {
-        match self.is_double_qubit() {
+        match self.is_double_qubit() && !self.is_measurement() {
             true => (self.qubit1, Some(self.qubit2)),
             false => (self.qubit1, None),
         }
     }
 
-    fn write<W: io::Write>(&self, writer: &mut W, num_qubits: u32) -> io::Result<()> {
-        let qubit_bits = (32 - (num_qubits - 1).leading_zeros()) as usize;
-        let mut bit_buf = BitBuffer::new();
-        bit_buf.write_bits(self.gate_type as u64, Gate::op_bits());
-        bit_buf.write_bits(self.qubit1 as u64, qubit_bits);
-        if self.is_double_qubit() {
-            bit_buf.write_bits(self.qubit2 as u64, qubit_bits);
-        }
-
-        writer.write_all(bit_buf.bytes())?;
-
-        Ok(())
-    }
-
+    /// Reads a single byte-aligned gate from the legacy `version = 1`
+    /// layout, where every gate flushes to its own byte boundary. Predates
+    /// parametric gates, so no angle field is read.
     fn read<R: io::Read>(reader: &mut R, num_qubits: u32) -> io::Result<Self> {
         let qubit_bits = (32 - (num_qubits - 1).leading_zeros()) as usize;
         let mut byte_buf = [0u8; 1];
         reader.read_exact(&mut byte_buf)?;
         let mut bit_reader = BitReader::new(Vec::from(byte_buf));
-        let gate_type = bit_reader.read_bits(Gate::op_bits()) as u8;
+        let gate_type = bit_reader.read_bits(Gate::LEGACY_V1_OP_BITS)? as u8;
         let is_double_qubit = gate_type == 1;
 
         let remaining_byte_size =
-            (qubit_bits * ((is_double_qubit as usize) + 1) + Gate::op_bits() + 7) / 8 - 1;
+            (qubit_bits * ((is_double_qubit as usize) + 1) + Gate::LEGACY_V1_OP_BITS + 7) / 8 - 1;
         let mut vec_buf = vec![0u8; remaining_byte_size];
         reader.read_exact(&mut vec_buf)?;
         bit_reader.append(&mut vec_buf);
 
         // read qubit size
-        let qubit1 = bit_reader.read_bits(qubit_bits) as u32;
+        let qubit1 = bit_reader.read_bits(qubit_bits)? as u32;
         let qubit2 = match is_double_qubit {
-            true => bit_reader.read_bits(qubit_bits) as u32,
+            true => bit_reader.read_bits(qubit_bits)? as u32,
             false => 0,
         };
 
@@ -160,19 +233,120 @@ impl Gate {
             gate_type,
             qubit1,
             qubit2,
+            angle: None,
+        })
+    }
+
+    /// Writes this gate's fields into `bit_buf` back-to-back with no
+    /// inter-gate padding, used by the circuit-level `version >= 2` layout.
+    /// Parametric gates (`version >= 3`) additionally append their angle
+    /// as a 64-bit IEEE-754 field. A `measure` gate's classical-bit index
+    /// (`version >= 4`) is encoded with `classical_bits` rather than
+    /// `qubit_bits`, since the classical address space is sized
+    /// independently of `num_qubits`.
+    fn write_packed(&self, bit_buf: &mut BitBuffer, qubit_bits: usize, classical_bits: usize) {
+        bit_buf.write_bits(self.gate_type as u64, Gate::op_bits());
+        bit_buf.write_bits(self.qubit1 as u64, qubit_bits);
+        if self.is_measurement() {
+            bit_buf.write_bits(self.qubit2 as u64, classical_bits);
+        } else if self.is_double_qubit() {
+            bit_buf.write_bits(self.qubit2 as u64, qubit_bits);
+        }
+        if let Some(angle) = self.angle {
+            bit_buf.write_bits(angle.to_bits(), Gate::angle_bits());
+        }
+    }
+
+    /// Reads one gate out of a shared, already-loaded `BitReader`, used by
+    /// the circuit-level `version >= 2` layout. `version` decides whether a
+    /// parametric gate's angle field is present to read; `classical_bits`
+    /// is the width a `measure` gate's classical-bit index was encoded
+    /// with (see `write_packed`). Returns an `UnexpectedEof` error rather
+    /// than panicking if the bitstream runs out, so a truncated or corrupt
+    /// file is reported instead of aborting the process.
+    fn read_packed(
+        bit_reader: &mut BitReader,
+        qubit_bits: usize,
+        classical_bits: usize,
+        version: u16,
+    ) -> io::Result<Self> {
+        let gate_type = bit_reader.read_bits(Gate::op_bits())? as u8;
+        let is_double_qubit = DOUBLE_QUBIT_TAGS.contains(&gate_type);
+        let is_measurement = gate_type == Gate::MEASURE_TAG;
+        let qubit1 = bit_reader.read_bits(qubit_bits)? as u32;
+        let qubit2 = if is_measurement {
+            bit_reader.read_bits(classical_bits)? as u32
+        } else if is_double_qubit {
+            bit_reader.read_bits(qubit_bits)? as u32
+        } else {
+            0
+        };
+
+        let is_parametric = version >= 3 && PARAMETRIC_TAGS.contains(&gate_type);
+        let angle = match is_parametric {
+            true => Some(f64::from_bits(bit_reader.read_bits(Gate::angle_bits())?)),
+            false => None,
+        };
+
+        Ok(Gate {
+            gate_type,
+            qubit1,
+            qubit2,
+            angle,
         })
     }
 }
 
+/// Smallest bit width that can represent every value in `0..count`, matching
+/// the convention used for `qubit_bits` (`count == 0` needs no bits).
+fn bits_for(count: u32) -> usize {
+    if count == 0 {
+        0
+    } else {
+        (32 - (count - 1).leading_zeros()) as usize
+    }
+}
+
+/// Size of the used qubit address space: one past the highest touched
+/// index, not the number of distinct qubits touched. `qubits` only records
+/// *which* indices a circuit uses, so a circuit touching just `q[2]` still
+/// needs width for indices `0..=2` or that index gets truncated on write.
+fn used_qubit_count(qubits: &HashSet<u32>) -> u32 {
+    qubits.iter().max().map_or(0, |&m| m + 1)
+}
+
+/// Size of the classical address space a circuit's `measure` gates and
+/// classical `RegisterDescriptor`s need, as one past the highest index
+/// either mentions. Registers alone aren't enough: a circuit built
+/// programmatically (no parser, no `creg`) can still carry `measure` gates,
+/// so the gate list is also consulted.
+fn classical_extent(registers: &[RegisterDescriptor], gates: &[Gate]) -> u32 {
+    let register_extent = registers
+        .iter()
+        .filter(|r| r.is_classical)
+        .map(|r| r.offset + r.size)
+        .max()
+        .unwrap_or(0);
+    let gate_extent = gates
+        .iter()
+        .filter_map(Gate::measured_bit)
+        .map(|bit| bit + 1)
+        .max()
+        .unwrap_or(0);
+    register_extent.max(gate_extent)
+}
+
 pub struct Circuit {
     pub gates: Vec<Gate>,
     pub qubits: HashSet<u32>,
+    pub registers: Vec<RegisterDescriptor>,
 }
 impl Circuit {
     pub fn new() -> Self {
         Circuit {
             gates: Vec::new(),
             qubits: HashSet::new(),
+            registers: Vec::new(),
         }
     }
     pub fn add_gate(&mut self, gate: Gate) {
@@ -188,21 +362,54 @@ impl Circuit {
         self.gates.push(gate);
     }
 
+    pub fn add_register(&mut self, register: RegisterDescriptor) {
+        self.registers.push(register);
+    }
+
     pub fn write<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
-        let header = Header::new(self.qubits.len() as u32, self.gates.len() as u64);
+        let header = Header::new(
+            used_qubit_count(&self.qubits),
+            self.gates.len() as u64,
+            classical_extent(&self.registers, &self.gates),
+            self.registers.clone(),
+        );
         header.write(writer)?;
+
+        let qubit_bits = bits_for(header.num_qubits);
+        let classical_bits = bits_for(header.num_classical);
+        let mut bit_buf = BitBuffer::new();
         self.gates
             .iter()
-            .try_for_each(|g| g.write(writer, header.num_qubits))?;
+            .for_each(|g| g.write_packed(&mut bit_buf, qubit_bits, classical_bits));
+        writer.write_all(bit_buf.bytes())?;
+
         Ok(())
     }
 
     pub fn read<R: io::Read>(reader: &mut R) -> io::Result<Self> {
-        let header = Header::read(reader)?;
+        let mut header = Header::read(reader)?;
         let mut circuit = Circuit::new();
-        for _ in 0..header.num_gates {
-            let gate = Gate::read(reader, header.num_qubits)?;
-            circuit.add_gate(gate);
+        circuit.registers = std::mem::take(&mut header.registers);
+
+        if header.version >= 2 {
+            let qubit_bits = bits_for(header.num_qubits);
+            let classical_bits = bits_for(header.num_classical);
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            let mut bit_reader = BitReader::new(data);
+            for _ in 0..header.num_gates {
+                circuit.add_gate(Gate::read_packed(
+                    &mut bit_reader,
+                    qubit_bits,
+                    classical_bits,
+                    header.version,
+                )?);
+            }
+        } else {
+            for _ in 0..header.num_gates {
+                let gate = Gate::read(reader, header.num_qubits)?;
+                circuit.add_gate(gate);
+            }
         }
 
         Ok(circuit)
@@ -260,13 +467,19 @@ impl BitReader {
         self.data.append(new_data);
     }
 
-    fn read_bits(&mut self, mut bits: usize) -> u64 {
+    /// Reads `bits` bits starting at the current position, or an
+    /// `UnexpectedEof` error if the buffer runs out first (a truncated or
+    /// corrupt file rather than a bug, so this must not panic).
+    fn read_bits(&mut self, mut bits: usize) -> io::Result<u64> {
         let mut value = 0u64;
         let mut shift = 0;
 
         while bits > 0 {
             if self.bit_pos >= self.data.len() * 8 {
-                panic!();
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "truncated gate bitstream",
+                ));
             }
 
             let byte_index = self.bit_pos / 8;
@@ -283,6 +496,101 @@ impl BitReader {
             bits -= min_bits;
         }
 
-        value
+        Ok(value)
+    }
+}
+
+// Gate type tags, constructors, `op_bits()`, and the QASM name tables are
+// generated from `gates.in` by `build.rs`.
+include!(concat!(env!("OUT_DIR"), "/gates_generated.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_the_packed_format() {
+        let mut circuit = Circuit::new();
+        circuit.add_gate(Gate::h(0));
+        circuit.add_gate(Gate::cx(0, 1));
+        circuit.add_gate(Gate::rz(1, 0.5));
+
+        let mut bytes = Vec::new();
+        circuit.write(&mut bytes).unwrap();
+        let read_back = Circuit::read(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(read_back.gates.len(), circuit.gates.len());
+        for (original, roundtripped) in circuit.gates.iter().zip(read_back.gates.iter()) {
+            assert_eq!(original.gate_type, roundtripped.gate_type);
+            assert_eq!(original.qubit1, roundtripped.qubit1);
+            assert_eq!(original.qubit2, roundtripped.qubit2);
+            assert_eq!(original.angle, roundtripped.angle);
+        }
+    }
+
+    #[test]
+    fn sparse_qubit_indices_survive_the_round_trip() {
+        // Only q[2] is ever touched, so `qubits = {2}` — the field width
+        // must still cover indices `0..=2`, not just the one used value.
+        let mut circuit = Circuit::new();
+        circuit.add_gate(Gate::h(2));
+
+        let mut bytes = Vec::new();
+        circuit.write(&mut bytes).unwrap();
+        let read_back = Circuit::read(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(read_back.gates[0].qubit1, 2);
+    }
+
+    #[test]
+    fn measure_without_classical_registers_keeps_its_bit() {
+        // No `RegisterDescriptor`s at all; `num_classical` must still be
+        // derived from the gate list so the classical bit round-trips.
+        let mut circuit = Circuit::new();
+        circuit.add_gate(Gate::measure(0, 3));
+
+        let mut bytes = Vec::new();
+        circuit.write(&mut bytes).unwrap();
+        let read_back = Circuit::read(&mut bytes.as_slice()).unwrap();
+
+        assert_eq!(read_back.gates[0].qubit2, 3);
+    }
+
+    #[test]
+    fn legacy_v1_gates_decode_with_the_pinned_opcode_width() {
+        // Hand-assembled v1 file: header + one byte-aligned `h q[0]` gate
+        // (gate_type 2, LEGACY_V1_OP_BITS = 2, qubit_bits = 1 for 2 qubits).
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"DQASM\0");
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // version
+        bytes.extend_from_slice(&2u32.to_le_bytes()); // num_qubits
+        bytes.extend_from_slice(&1u64.to_le_bytes()); // num_gates
+
+        let mut bit_buf = BitBuffer::new();
+        bit_buf.write_bits(2, Gate::LEGACY_V1_OP_BITS); // gate_type = h
+        bit_buf.write_bits(0, 1); // qubit1
+        bytes.extend_from_slice(bit_buf.bytes());
+
+        let circuit = Circuit::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(circuit.gates.len(), 1);
+        assert_eq!(circuit.gates[0].gate_type, 2);
+        assert_eq!(circuit.gates[0].qubit1, 0);
+    }
+
+    #[test]
+    fn truncated_packed_stream_is_an_error_not_a_panic() {
+        let mut circuit = Circuit::new();
+        circuit.add_gate(Gate::cx(0, 1));
+
+        let mut bytes = Vec::new();
+        circuit.write(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() - 1);
+
+        let result = Circuit::read(&mut bytes.as_slice());
+        assert!(result.is_err());
+        assert_eq!(
+            result.unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
     }
 }